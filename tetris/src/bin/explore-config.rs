@@ -0,0 +1,172 @@
+//! Headless self-play driver for tuning `Weights`. Each round mutates the current best weight
+//! set into a candidate, then runs `MATCHES_PER_EVAL` head-to-head matches: one `Bot` per weight
+//! set, playing side by side on independent boards for the same number of pieces, with whichever
+//! scores higher on pieces survived plus ten per line of garbage sent winning the match. The candidate replaces the
+//! incumbent once it wins a majority of the matches, so every comparison is a fresh candidate
+//! vs. the current best rather than two cached numbers from different rounds. Run with
+//! `cargo run --bin explore-config`.
+
+use tetris::bot::Bot;
+use tetris::players::Player;
+use tetris::weight::Weights;
+
+const PIECES_PER_MATCH: usize = 300;
+const MATCHES_PER_EVAL: usize = 8;
+const HILL_CLIMB_ROUNDS: usize = 50;
+const MUTATION_STEP: f32 = 0.05;
+
+fn main() {
+    let mut best = Weights::default();
+    let mut seed: u64 = 0x9E3779B97F4A7C15;
+
+    for round in 0..HILL_CLIMB_ROUNDS {
+        let candidate = mutate(&best, &mut seed);
+
+        let wins = (0..MATCHES_PER_EVAL)
+            .filter(|_| play_match(&candidate, &best).candidate_won())
+            .count();
+
+        if wins * 2 > MATCHES_PER_EVAL {
+            println!("round {round}: candidate won {wins}/{MATCHES_PER_EVAL} matches - adopting");
+            best = candidate;
+        } else {
+            println!("round {round}: candidate won {wins}/{MATCHES_PER_EVAL} matches - keeping current weights");
+        }
+    }
+}
+
+/// Result of one head-to-head match: pieces survived and garbage sent for each side.
+struct MatchResult {
+    candidate_pieces: usize,
+    candidate_garbage: f32,
+    incumbent_pieces: usize,
+    incumbent_garbage: f32,
+}
+
+impl MatchResult {
+    /// Each side scores pieces survived plus ten per line of garbage sent, and the candidate wins
+    /// only with a strictly higher score: with equal garbage the longer survivor wins, equal
+    /// survival goes to whichever sent more garbage, and a stronger attacker that also tops out
+    /// isn't penalized against a passive stacker that merely outlasts it.
+    fn candidate_won(&self) -> bool {
+        let candidate_score = self.candidate_pieces as f32 + self.candidate_garbage * 10.0;
+        let incumbent_score = self.incumbent_pieces as f32 + self.incumbent_garbage * 10.0;
+        candidate_score > incumbent_score
+    }
+}
+
+/// Plays `candidate` and `incumbent` side by side on independent boards for up to
+/// `PIECES_PER_MATCH` pieces each, stopping early once both have topped out.
+fn play_match(candidate: &Weights, incumbent: &Weights) -> MatchResult {
+    let mut candidate_bot = Bot::with_weight(candidate.clone());
+    let mut incumbent_bot = Bot::with_weight(incumbent.clone());
+
+    let mut candidate_pieces = 0;
+    let mut candidate_garbage = 0.0;
+    let mut incumbent_pieces = 0;
+    let mut incumbent_garbage = 0.0;
+
+    for _ in 0..PIECES_PER_MATCH {
+        if candidate_bot.make_move() {
+            candidate_garbage += candidate_bot.get_game().data.last_sent as f32;
+            candidate_pieces += 1;
+        }
+
+        if incumbent_bot.make_move() {
+            incumbent_garbage += incumbent_bot.get_game().data.last_sent as f32;
+            incumbent_pieces += 1;
+        }
+
+        if candidate_bot.get_game().get_game_over() && incumbent_bot.get_game().get_game_over() {
+            break;
+        }
+    }
+
+    MatchResult {
+        candidate_pieces,
+        candidate_garbage,
+        incumbent_pieces,
+        incumbent_garbage,
+    }
+}
+
+/// Perturbs one randomly chosen weight coefficient by a small random step. This is the whole
+/// search move: a hill-climb over `Weights`' fields rather than anything smarter, since
+/// `play_match` is the expensive part and a simple perturb-and-keep loop is enough to replace
+/// hand-setting `Weights::default()`.
+fn mutate(weight: &Weights, seed: &mut u64) -> Weights {
+    let mut next = weight.clone();
+    let step = (next_random(seed) as f32 / u64::MAX as f32 - 0.5) * 2.0 * MUTATION_STEP;
+
+    match next_random(seed) % 10 {
+        0 => next.height_weight.0 += step,
+        1 => next.num_hole_total_weight.0 += step,
+        2 => next.num_hole_weighted_weight.0 += step,
+        3 => next.cell_covered_weight.0 += step,
+        4 => next.adjacent_height_differences_weight.0 += step,
+        5 => next.total_height_difference_weight.0 += step,
+        6 => next.combo_weight.0 += step,
+        7 => next.b2b_weight.0 += step,
+        8 => next.damage_weight.0 += step,
+        _ => next.clear_weight.0 += step,
+    }
+
+    next
+}
+
+/// xorshift64: no dependency needed just to pick a field and a step size.
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidate_wins_by_surviving_longer() {
+        let result = MatchResult {
+            candidate_pieces: 300,
+            candidate_garbage: 0.0,
+            incumbent_pieces: 250,
+            incumbent_garbage: 0.0,
+        };
+        assert!(result.candidate_won());
+    }
+
+    #[test]
+    fn candidate_loses_by_surviving_shorter() {
+        let result = MatchResult {
+            candidate_pieces: 250,
+            candidate_garbage: 0.0,
+            incumbent_pieces: 300,
+            incumbent_garbage: 0.0,
+        };
+        assert!(!result.candidate_won());
+    }
+
+    #[test]
+    fn tied_survival_is_broken_by_garbage_sent() {
+        let result = MatchResult {
+            candidate_pieces: 300,
+            candidate_garbage: 5.0,
+            incumbent_pieces: 300,
+            incumbent_garbage: 2.0,
+        };
+        assert!(result.candidate_won());
+    }
+
+    #[test]
+    fn tied_survival_and_garbage_is_not_a_win() {
+        let result = MatchResult {
+            candidate_pieces: 300,
+            candidate_garbage: 2.0,
+            incumbent_pieces: 300,
+            incumbent_garbage: 2.0,
+        };
+        assert!(!result.candidate_won());
+    }
+}
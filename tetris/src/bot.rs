@@ -1,21 +1,37 @@
 #![allow(dead_code)]
 
+use std::collections::{HashSet, VecDeque};
 use std::fmt::{Display, Formatter};
 use std::iter::zip;
+use std::time::{Duration, Instant};
+use rayon::prelude::*;
 use crate::board::Board;
-use crate::constants::bot_constants::{Command, ROTATIONS};
-use crate::constants::piece_constants::NUM_ROTATE_STATES;
+use crate::constants::bot_constants::Command;
 use crate::constants::types::*;
-use crate::players::{Player, do_command};
+use crate::piece::{Piece, PieceType};
+use crate::players::{Player, do_command, do_move_list};
 use crate::weight::Weights;
 use crate::game::{Game, GameData};
-use crate::piece::Piece;
 
 pub struct Bot {
     game: Game,
     weight: Weights,
 }
 
+/// How many immediate candidates survive beam pruning before a deeper ply gets searched.
+const BEAM_WIDTH: usize = 10;
+
+/// Compact dedupe key for the active piece's position during reachability search.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct PlacementKey(Col, Row, usize);
+
+impl PlacementKey {
+    fn of(game: &Game) -> Self {
+        let piece = &game.active_piece;
+        PlacementKey(piece.col, piece.row, piece.rotation)
+    }
+}
+
 impl Display for Bot {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.game)?;
@@ -32,6 +48,7 @@ impl Default for Bot {
     }
 }
 
+
 impl Player for Bot {
     fn get_game(&self) -> &Game {
         &self.game
@@ -42,90 +59,396 @@ impl Player for Bot {
     }
 
     fn get_next_move(&mut self) -> CommandList {
-        let (deep_moves, _, deep_scores) = self.move_placement_score(3, &self.weight.clone());
-        let deep_scores: Vec<f32> = deep_scores.iter().map(|(board, versus)| board+versus).collect();
+        self.next_move_and_spin().0
+    }
+
+    /// Overridden so the `HardDrop` committed to the live `self.game` gets the same
+    /// guideline correction search results already get: the engine's own `hard_drop()`
+    /// (reached via the default `make_move`'s `do_move_list`) has no notion of a T-spin, so
+    /// without this the live game's `data.last_sent` would silently disagree with the value
+    /// the search actually picked the move on.
+    fn make_move(&mut self) -> bool {
+        if self.get_game().get_game_over() {
+            return false;
+        }
+
+        let (action, is_spin) = self.next_move_and_spin();
+        do_move_list(&mut self.game, action);
+        Bot::correct_last_sent(&mut self.game, is_spin);
+        true
+    }
+}
+
+impl Bot {
+    /// Builds a bot around a fresh game with a caller-supplied weight set, e.g. for self-play
+    /// harnesses that tune `Weights` by comparing two `Bot`s against each other.
+    pub fn with_weight(weight: Weights) -> Self {
+        Self {
+            game: Game::new(None),
+            weight,
+        }
+    }
+
+    /// Anytime version of `get_next_move`: iterative-deepening search that keeps the best
+    /// move found at each completed depth and stops as soon as `budget` has elapsed, returning
+    /// the deepest complete-depth result. Mirrors the time-bounded `choose_move(start_time,
+    /// max_time)` loop pattern so the bot scales search depth to whatever per-move time it is
+    /// given instead of a hard-coded depth. The `deadline` is also threaded into
+    /// `move_placement_score_nd` itself, so a depth that doesn't fit in `budget` is abandoned
+    /// as soon as the ply in flight finishes rather than being run to completion first.
+    pub fn get_next_move_timed(&mut self, budget: Duration) -> CommandList {
+        let deadline = Instant::now() + budget;
+        let weight = self.weight.clone();
+
+        let mut dummy = self.game.clone();
+        let (moves, _, scores, _) = Bot::move_placement_score_nd(&mut dummy, 1, &weight, Some(deadline));
+        let mut best = Bot::best_moves(moves, scores);
+
+        let mut depth = 2;
+        while Instant::now() < deadline {
+            let mut dummy = self.game.clone();
+            let (moves, _, scores, _) = Bot::move_placement_score_nd(&mut dummy, depth, &weight, Some(deadline));
 
+            if Instant::now() >= deadline {
+                break;
+            }
+
+            best = Bot::best_moves(moves, scores);
+            depth += 1;
+        }
+
+        best.push(Command::HardDrop);
+        best
+    }
+
+    /// Same search `get_next_move` runs, but also returns whether the chosen placement was a
+    /// spin, so `make_move` can correct the live game's `data.last_sent` once it actually
+    /// commits the move instead of only the search's own throwaway clones.
+    fn next_move_and_spin(&mut self) -> (CommandList, bool) {
+        let (deep_moves, _, deep_scores, spins) = self.move_placement_score(3, &self.weight.clone());
         let mut min_score = f32::INFINITY;
-        let mut action = vec![];
+        let mut best_index = None;
 
-        for (moves, score) in zip(deep_moves, deep_scores) {
-            if score < min_score {
-                min_score = score;
-                action = moves;
+        for (i, &score) in deep_scores.iter().enumerate() {
+            let total = Bot::total_score(score);
+            if total < min_score {
+                min_score = total;
+                best_index = Some(i);
             }
         }
 
+        let Some(index) = best_index else {
+            return (vec![Command::HardDrop], false);
+        };
+
+        let mut action = deep_moves[index].clone();
         action.push(Command::HardDrop);
+        (action, spins[index])
+    }
+
+    fn best_moves(moves: MoveList, scores: ScoreList) -> CommandList {
+        let mut min_score = f32::INFINITY;
+        let mut action = vec![];
+
+        for (candidate, score) in zip(moves, scores) {
+            let total = Bot::total_score(score);
+            if total < min_score {
+                min_score = total;
+                action = candidate;
+            }
+        }
+
         action
     }
-}
 
-impl Bot {
     // move gen
-    fn move_placement_score(&mut self, depth: usize, weight: &Weights) -> (MoveList, PlacementList, ScoreList) {
+    fn move_placement_score(&mut self, depth: usize, weight: &Weights) -> (MoveList, PlacementList, ScoreList, Vec<bool>) {
         let mut dummy = self.game.clone();
-        Bot::move_placement_score_1d(&mut dummy, weight)
+        Bot::move_placement_score_nd(&mut dummy, depth.max(1), weight, None)
     }
 
-    fn move_placement_score_1d(game: &mut Game, weight: &Weights) -> (MoveList, PlacementList, ScoreList) {
-        Bot::trivial(game, false, weight)
+    pub(crate) fn move_placement_score_1d(game: &mut Game, weight: &Weights) -> (MoveList, PlacementList, ScoreList, Vec<bool>) {
+        let (mut moves, mut placements, mut scores, mut spins) = Bot::reachable_placements(game, false, weight);
+        let (hold_moves, hold_placements, hold_scores, hold_spins) = Bot::reachable_placements(game, true, weight);
+
+        moves.extend(hold_moves);
+        placements.extend(hold_placements);
+        scores.extend(hold_scores);
+        spins.extend(hold_spins);
+
+        (moves, placements, scores, spins)
     }
 
-    fn trivial(game: &mut Game, hold: bool, weight: &Weights) -> (MoveList, PlacementList, ScoreList) {
-        let mut moves = Vec::with_capacity(40);
-        let mut placements = Vec::with_capacity(40);
-        let mut scores = Vec::with_capacity(40);
+    /// Depth-limited search over `move_placement_score_1d`'s candidates: each placement is
+    /// locked onto a cloned `Game`, which advances to the next queue piece for free, and the
+    /// search recurses to `depth - 1`, accumulating the minimum board+versus score along the
+    /// path. To keep this tractable only the `BEAM_WIDTH` best immediate candidates (by their
+    /// own `score_board`) are carried into the next ply; the rest are pruned before recursing.
+    ///
+    /// `deadline` (when set) is checked before recursing into the next ply, not just between
+    /// top-level calls: since each of up to `BEAM_WIDTH` branches recurses into another full
+    /// `move_placement_score_1d` BFS, an unchecked recursion can blow well past a caller's time
+    /// budget. Once the deadline has passed, recursion stops and this ply's own (already-paid-for)
+    /// 1-ply scores are returned as-is instead of starting another, more expensive ply.
+    fn move_placement_score_nd(game: &mut Game, depth: usize, weight: &Weights, deadline: Option<Instant>) -> (MoveList, PlacementList, ScoreList, Vec<bool>) {
+        let (moves, placements, scores, spins) = Bot::move_placement_score_1d(game, weight);
+
+        let out_of_time = match deadline {
+            Some(deadline) => Instant::now() >= deadline,
+            None => false,
+        };
+
+        if depth <= 1 || moves.is_empty() || out_of_time {
+            return (moves, placements, scores, spins);
+        }
 
-        for direction in 0..NUM_ROTATE_STATES {
-            if !game.active_piece_rotate_direction(direction) {
-                continue;
-            }
+        let mut beam: Vec<usize> = (0..moves.len()).collect();
+        beam.sort_by(|&a, &b| scores[a].0.partial_cmp(&scores[b].0).unwrap());
+        beam.truncate(BEAM_WIDTH);
+
+        // Each beam candidate clones `game` and recurses independently, so scoring the beam is
+        // embarrassingly parallel; collect the candidates first and hand them to rayon.
+        let results: Vec<_> = beam
+            .par_iter()
+            .map(|&idx| {
+                let mut next_game = game.clone();
+                do_move_list(&mut next_game, moves[idx].clone());
+                Bot::lock_piece(&mut next_game, spins[idx]);
+
+                let (_, _, child_scores, _) = Bot::move_placement_score_nd(&mut next_game, depth - 1, weight, deadline);
+                let best_child = child_scores
+                    .iter()
+                    .map(|&score| Bot::total_score(score))
+                    .fold(f32::INFINITY, f32::min);
+
+                let (board, versus) = scores[idx];
+                let accumulated = if best_child.is_finite() {
+                    board + versus + best_child
+                } else {
+                    board + versus
+                };
+
+                (moves[idx].clone(), placements[idx].clone(), (accumulated, 0.0), spins[idx])
+            })
+            .collect();
+
+        let mut out_moves = Vec::with_capacity(results.len());
+        let mut out_placements = Vec::with_capacity(results.len());
+        let mut out_scores = Vec::with_capacity(results.len());
+        let mut out_spins = Vec::with_capacity(results.len());
+
+        for (candidate_move, placement, score, spin) in results {
+            out_moves.push(candidate_move);
+            out_placements.push(placement);
+            out_scores.push(score);
+            out_spins.push(spin);
+        }
 
-            let mut base_move;
-            if hold {
-                base_move = vec![Command::Hold, ROTATIONS[direction]];
-            } else {
-                base_move = vec![ROTATIONS[direction]];
-            }
+        (out_moves, out_placements, out_scores, out_spins)
+    }
 
-            Bot::trivial_extend_direction(&mut moves, &mut placements, &mut scores,
-                                          base_move.clone(), Command::MoveLeft, game, weight);
-            Bot::trivial_extend_direction(&mut moves, &mut placements, &mut scores,
-                                          base_move.clone(), Command::MoveRight, game, weight);
+    fn total_score(score: (Score, Score)) -> Score {
+        score.0 + score.1
+    }
 
-            game.active_piece_rotate_direction((NUM_ROTATE_STATES - direction) % NUM_ROTATE_STATES);
+    /// Every locking placement reachable from the active piece (or the held piece, when
+    /// `hold` is true), found by BFS over `(col, row, rotation)` states instead of the old
+    /// rotate-then-slide-then-drop approximation. Each edge is a real `do_command` against a
+    /// cloned `Game`, so tucks, spins, and kicks all fall out for free instead of needing to be
+    /// special-cased. A state is terminal once `SoftDrop` no longer succeeds (the cell below is
+    /// blocked); terminal states are deduped by the resulting locked board so two command paths
+    /// that land the same shape in the same place only count once. The returned `Vec<bool>`
+    /// flags placements whose last successful move was a rotation with the piece otherwise
+    /// stuck *and* whose piece is a T — the signal `score_versus` needs to recognize a T-spin,
+    /// as opposed to some other shape merely getting wedged into a notch after a rotation.
+    fn reachable_placements(game: &mut Game, hold: bool, weight: &Weights)
+        -> (MoveList, PlacementList, ScoreList, Vec<bool>)
+    {
+        let terminal = Bot::reachable_terminal_states(game, hold);
+
+        // Terminal candidates are independent of each other, so score them all in parallel.
+        // Each `locked` game already carries the guideline-corrected `data.last_sent` from
+        // `reachable_terminal_states`'s own `lock_piece` call, so scoring just reads it back.
+        let scored: Vec<(Score, Score)> = terminal
+            .par_iter()
+            .map(|(_, locked, _, _)| Bot::score_game(locked, weight))
+            .collect();
+
+        let mut moves = Vec::with_capacity(terminal.len());
+        let mut placements = Vec::with_capacity(terminal.len());
+        let mut scores = Vec::with_capacity(terminal.len());
+        let mut used_rotation_last = Vec::with_capacity(terminal.len());
+
+        for ((path, _, piece, is_spin), score) in zip(terminal, scored) {
+            moves.push(path);
+            placements.push(piece);
+            scores.push(score);
+            used_rotation_last.push(is_spin);
         }
 
-        (moves, placements, scores)
+        (moves, placements, scores, used_rotation_last)
     }
 
-    fn trivial_extend_direction(moves: &mut MoveList, placements: &mut PlacementList, scores: &mut ScoreList,
-                                mut base_move: CommandList, command: Command,
-                                game: &mut Game, weight: &Weights) {
-        while do_command(game, command) {
-            let piece = game.ret_active_piece_drop();
-            scores.push(Bot::score_game(game.clone(), weight, &piece));
-            placements.push(piece);
-            base_move.push(command);
-            base_move.push(Command::SoftDrop);
-            moves.push(base_move.clone())
+    /// Every distinct command path that locks the active piece (or the held piece, when `hold`
+    /// is true) somewhere new, as the `(command path, locked game, dropped piece, is_spin)`
+    /// BFS frontier that `reachable_placements` scores. Split out on its own so callers that
+    /// only need to sample a placement — rollouts, mainly — aren't forced to pay for
+    /// `score_game`'s rayon-parallel scoring pass over every candidate just to throw it away.
+    fn reachable_terminal_states(game: &mut Game, hold: bool) -> Vec<(CommandList, Game, Piece, bool)> {
+        const SEARCH: [Command; 6] = [
+            Command::MoveLeft,
+            Command::MoveRight,
+            Command::SoftDrop,
+            Command::RotateCW,
+            Command::RotateCCW,
+            Command::Rotate180,
+        ];
+
+        let mut root = game.clone();
+        let base_move: CommandList = if hold {
+            root.hold();
+            vec![Command::Hold]
+        } else {
+            vec![]
+        };
+
+        let mut seen_locks: HashSet<Board> = HashSet::new();
+        let mut visited: HashSet<PlacementKey> = HashSet::new();
+        visited.insert(PlacementKey::of(&root));
+
+        let mut queue = VecDeque::new();
+        queue.push_back((root, base_move, false));
+
+        let mut terminal = Vec::with_capacity(64);
+
+        while let Some((state, path, is_spin)) = queue.pop_front() {
+            let mut down_probe = state.clone();
+            if !do_command(&mut down_probe, Command::SoftDrop) {
+                let piece = state.clone().ret_active_piece_drop();
+
+                // Guideline "all-spin" scoring only applies to T-pieces; any other shape
+                // wedged into a notch right after a rotation is an immobile placement, not
+                // a T-spin, and shouldn't be scored through attack_sent's T-spin table.
+                let spin = is_spin && piece.kind == PieceType::T;
+
+                let mut locked = state.clone();
+                Bot::lock_piece(&mut locked, spin);
+
+                if seen_locks.insert(locked.board.clone()) {
+                    terminal.push((path.clone(), locked, piece, spin));
+                }
+            }
+
+            let move_left_ok = do_command(&mut state.clone(), Command::MoveLeft);
+            let move_right_ok = do_command(&mut state.clone(), Command::MoveRight);
+            let soft_drop_ok = do_command(&mut state.clone(), Command::SoftDrop);
+            let immobile = !move_left_ok && !move_right_ok && !soft_drop_ok;
+
+            for &command in SEARCH.iter() {
+                let mut next = state.clone();
+                if !do_command(&mut next, command) {
+                    continue;
+                }
+
+                if visited.insert(PlacementKey::of(&next)) {
+                    let mut next_path = path.clone();
+                    next_path.push(command);
+                    let is_rotation = matches!(
+                        command,
+                        Command::RotateCW | Command::RotateCCW | Command::Rotate180
+                    );
+                    queue.push_back((next, next_path, immobile && is_rotation));
+                }
+            }
         }
+
+        terminal
     }
 
+    /// Cheap counterpart to `move_placement_score_1d` for callers that only need to sample a
+    /// legal placement uniformly, not rank it: enumerates the same BFS frontier (active piece
+    /// plus held piece) but skips `score_game`'s rayon-parallel scoring pass entirely. Each
+    /// placement's `is_spin` flag comes along too, so a caller that locks the piece itself can
+    /// still go through `lock_piece` and get a guideline-correct `data.last_sent`.
+    pub(crate) fn reachable_moves_1d(game: &mut Game) -> (MoveList, Vec<bool>) {
+        let mut moves = Vec::new();
+        let mut spins = Vec::new();
+
+        for (path, _, _, spin) in Bot::reachable_terminal_states(game, false) {
+            moves.push(path);
+            spins.push(spin);
+        }
+
+        for (path, _, _, spin) in Bot::reachable_terminal_states(game, true) {
+            moves.push(path);
+            spins.push(spin);
+        }
+
+        (moves, spins)
+    }
 
     // scoring
-    fn score_game(game: Game, weights: &Weights, piece: &Piece) -> (Score, Score) {
-        let versus_score = 0.0;
-        (Bot::score_board(&game.board, weights), versus_score)
+
+    /// Hard-drops the active piece and corrects the resulting `data.last_sent` for the
+    /// guideline attack table in one step, so every call site that locks a piece — real play
+    /// included, not just the search's throwaway clones — ends up with the same
+    /// T-spin-aware number anything reading `data.last_sent` afterward expects to see.
+    pub(crate) fn lock_piece(game: &mut Game, is_spin: bool) {
+        do_command(game, Command::HardDrop);
+        Bot::correct_last_sent(game, is_spin);
     }
 
-    fn score_board(board: &Board, weights: &Weights) -> Score {
+    /// The `attack_sent` half of `lock_piece`, split out so a piece that was already locked
+    /// through the engine's own `hard_drop()` (as happens via `Player`'s default `make_move`)
+    /// can still have its telemetry corrected after the fact instead of being locked twice.
+    fn correct_last_sent(game: &mut Game, is_spin: bool) {
+        game.data.last_sent = Bot::attack_sent(&game.data, &game.board, is_spin).round() as u32;
+    }
+
+    /// Scores a `Game` that has just had a piece locked into it via `lock_piece`, so
+    /// `game.data.last_sent` already reflects the guideline-corrected attack for whatever was
+    /// just dropped instead of the engine's own spin-unaware computation.
+    pub(crate) fn score_game(game: &Game, weights: &Weights) -> (Score, Score) {
+        (Bot::score_board(&game.board, weights), Bot::score_versus(&game.data, weights))
+    }
+
+    /// Standard guideline attack table: garbage lines sent for the clear that just happened,
+    /// folding in the T-spin, back-to-back, combo, and perfect-clear bonuses.
+    fn attack_sent(game_data: &GameData, board: &Board, is_spin: bool) -> f32 {
+        let lines = game_data.last_cleared;
+        if lines == 0 {
+            return 0.0;
+        }
+
+        let base = if is_spin {
+            match lines {
+                1 => 2.0,
+                2 => 4.0,
+                _ => 6.0,
+            }
+        } else {
+            match lines {
+                1 => 0.0,
+                2 => 1.0,
+                3 => 2.0,
+                _ => 4.0,
+            }
+        };
+
+        let b2b_bonus = if game_data.b2b > 0 && (is_spin || lines == 4) { 1.0 } else { 0.0 };
+        let combo_bonus = (game_data.combo as f32 * 0.5).floor();
+        let perfect_clear_bonus = if board.get_max_height() == 0 { 10.0 } else { 0.0 };
+
+        base + b2b_bonus + combo_bonus + perfect_clear_bonus
+    }
+
+    pub(crate) fn score_board(board: &Board, weights: &Weights) -> Score {
         Bot::get_holes_and_cell_covered_score(board, weights)
             + Bot::get_height_score(board, weights)
             + Bot::get_height_differences_score(board, weights)
     }
 
-    fn score_versus(game_data: &GameData, weight: &Weights) -> Score {
+    pub(crate) fn score_versus(game_data: &GameData, weight: &Weights) -> Score {
         let combo_score = weight.combo_weight.eval(game_data.combo as f32);
         let b2b = weight.b2b_weight.eval(game_data.b2b as f32);
         let attack = weight.damage_weight.eval(game_data.last_sent as f32);
@@ -170,4 +493,57 @@ impl Bot {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_data(last_cleared: u32, combo: u32, b2b: u32) -> GameData {
+        let mut data = GameData::default();
+        data.last_cleared = last_cleared;
+        data.combo = combo;
+        data.b2b = b2b;
+        data
+    }
+
+    #[test]
+    fn attack_sent_no_clear_sends_nothing() {
+        let data = game_data(0, 0, 0);
+        let board = Board::default();
+        assert_eq!(Bot::attack_sent(&data, &board, false), 0.0);
+    }
+
+    #[test]
+    fn attack_sent_single_is_not_boosted_by_b2b() {
+        // Singles don't carry a back-to-back bonus; only spins and tetrises do. The board is
+        // empty, so only the perfect-clear bonus (10.0) is added to the single's base (0.0).
+        let data = game_data(1, 0, 1);
+        let board = Board::default();
+        assert_eq!(Bot::attack_sent(&data, &board, false), 0.0 + 10.0);
+    }
+
+    #[test]
+    fn attack_sent_tetris_with_b2b() {
+        let data = game_data(4, 0, 1);
+        let board = Board::default();
+        // tetris base (4.0) + b2b (1.0) + perfect-clear bonus (10.0) for the empty board
+        assert_eq!(Bot::attack_sent(&data, &board, false), 4.0 + 1.0 + 10.0);
+    }
+
+    #[test]
+    fn attack_sent_tspin_double_with_combo() {
+        let data = game_data(2, 3, 1);
+        let board = Board::default();
+        // t-spin double base (4.0) + b2b (1.0) + combo bonus floor(3 * 0.5) = 1.0
+        // + perfect-clear bonus (10.0) for the empty board
+        assert_eq!(Bot::attack_sent(&data, &board, true), 4.0 + 1.0 + 1.0 + 10.0);
+    }
+
+    #[test]
+    fn attack_sent_perfect_clear_bonus() {
+        let data = game_data(4, 0, 0);
+        let board = Board::default();
+        assert_eq!(Bot::attack_sent(&data, &board, false), 4.0 + 10.0);
+    }
+}
+
 
@@ -0,0 +1,275 @@
+#![allow(dead_code)]
+
+use std::fmt::{Display, Formatter};
+use std::iter::zip;
+use std::time::{Duration, Instant};
+use crate::bot::Bot;
+use crate::constants::bot_constants::Command;
+use crate::constants::types::*;
+use crate::players::{Player, do_move_list};
+use crate::weight::Weights;
+use crate::game::Game;
+
+/// Exploration constant `c` in the UCT formula.
+const EXPLORATION: f32 = 1.4;
+
+/// How many random placements a rollout plays down the known queue before scoring the board.
+const ROLLOUT_DEPTH: usize = 10;
+
+/// Default per-move search budget when a caller just wants `Player::get_next_move`.
+const DEFAULT_BUDGET: Duration = Duration::from_millis(500);
+
+/// Monte Carlo Tree Search player: an alternative to `Bot`'s greedy/beam scorer that spends a
+/// time budget building a search tree over placements instead of a fixed-depth lookahead. The
+/// tree is kept between turns by re-rooting at the child that was actually played, so search
+/// effort carries over turn to turn instead of being thrown away.
+pub struct MctsBot {
+    game: Game,
+    weight: Weights,
+    tree: Option<MctsNode>,
+    rng_state: u64,
+}
+
+impl Display for MctsBot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.game)?;
+        Ok(())
+    }
+}
+
+impl Default for MctsBot {
+    fn default() -> Self {
+        Self {
+            game: Game::new(None),
+            weight: Weights::default(),
+            tree: None,
+            rng_state: 0x9E3779B97F4A7C15,
+        }
+    }
+}
+
+impl Player for MctsBot {
+    fn get_game(&self) -> &Game {
+        &self.game
+    }
+
+    fn get_game_mut(&mut self) -> &mut Game {
+        &mut self.game
+    }
+
+    fn get_next_move(&mut self) -> CommandList {
+        self.search(DEFAULT_BUDGET)
+    }
+}
+
+/// One node of the search tree: the `Game` state it represents, the command path that produced
+/// it from its parent (empty for the root), and the placements from this state that have not
+/// yet been expanded into children.
+struct MctsNode {
+    game: Game,
+    command: CommandList,
+    visits: u32,
+    total_cost: f32,
+    children: Vec<MctsNode>,
+    unexpanded: Vec<(CommandList, bool)>,
+}
+
+impl MctsNode {
+    fn new(game: Game, command: CommandList) -> Self {
+        let mut state = game.clone();
+        let (moves, spins) = Bot::reachable_moves_1d(&mut state);
+        let unexpanded = zip(moves, spins).collect();
+
+        MctsNode {
+            game,
+            command,
+            visits: 0,
+            total_cost: 0.0,
+            children: Vec::new(),
+            unexpanded,
+        }
+    }
+}
+
+impl MctsBot {
+    pub fn new(game: Game, weight: Weights) -> Self {
+        Self {
+            game,
+            weight,
+            tree: None,
+            rng_state: 0x9E3779B97F4A7C15,
+        }
+    }
+
+    /// Run MCTS iterations until `budget` elapses, then return the command list (plus a final
+    /// `HardDrop`) of the root child with the most visits.
+    pub fn search(&mut self, budget: Duration) -> CommandList {
+        let start = Instant::now();
+
+        let mut root = match self.tree.take() {
+            Some(node) if node.game.board == self.game.board => node,
+            _ => MctsNode::new(self.game.clone(), vec![]),
+        };
+
+        while start.elapsed() < budget {
+            MctsBot::iterate(&mut root, &self.weight, &mut self.rng_state);
+        }
+
+        let chosen = root
+            .children
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, child)| child.visits)
+            .map(|(i, _)| i);
+
+        match chosen {
+            Some(i) => {
+                let child = root.children.swap_remove(i);
+                let mut action = child.command.clone();
+                self.tree = Some(child);
+                action.push(Command::HardDrop);
+                action
+            }
+            None => {
+                self.tree = None;
+                vec![Command::HardDrop]
+            }
+        }
+    }
+
+    /// One selection/expansion/rollout/backpropagation pass, returning the cost it backed up (on
+    /// expansion, the new child's own placement score plus its rollout) so the caller can
+    /// accumulate it into its own visit/cost totals.
+    fn iterate(node: &mut MctsNode, weight: &Weights, rng_state: &mut u64) -> f32 {
+        if let Some((command, is_spin)) = node.unexpanded.pop() {
+            let mut child_game = node.game.clone();
+            do_move_list(&mut child_game, command.clone());
+            Bot::lock_piece(&mut child_game, is_spin);
+
+            // The immediate placement's own guideline-scored value (this is what lets a
+            // T-spin actually register, since `data.last_sent` only reflects the most recent
+            // drop and would otherwise be overwritten by whatever `rollout` plays next) plus
+            // how the position continues to play out from there.
+            let (board, versus) = Bot::score_game(&child_game, weight);
+            let cost = board + versus + MctsBot::rollout(&child_game, weight, rng_state);
+
+            let mut child = MctsNode::new(child_game, command);
+            child.visits = 1;
+            child.total_cost = cost;
+            node.children.push(child);
+
+            node.visits += 1;
+            node.total_cost += cost;
+            return cost;
+        }
+
+        if node.children.is_empty() {
+            let cost = MctsBot::rollout(&node.game, weight, rng_state);
+            node.visits += 1;
+            node.total_cost += cost;
+            return cost;
+        }
+
+        let parent_visits = node.visits.max(1) as f32;
+        let selected = node
+            .children
+            .iter_mut()
+            .max_by(|a, b| {
+                MctsBot::uct(a, parent_visits)
+                    .partial_cmp(&MctsBot::uct(b, parent_visits))
+                    .unwrap()
+            })
+            .unwrap();
+
+        let cost = MctsBot::iterate(selected, weight, rng_state);
+        node.visits += 1;
+        node.total_cost += cost;
+        cost
+    }
+
+    /// UCT score, negated because lower `score_board`/`score_versus` is better.
+    fn uct(child: &MctsNode, parent_visits: f32) -> f32 {
+        if child.visits == 0 {
+            return f32::INFINITY;
+        }
+
+        let visits = child.visits as f32;
+        let mean_cost = child.total_cost / visits;
+        -mean_cost + EXPLORATION * (parent_visits.ln() / visits).sqrt()
+    }
+
+    /// Play up to `ROLLOUT_DEPTH` random placements down the known piece queue from `game`,
+    /// then score the final board. Samples from `Bot::reachable_moves_1d`, which enumerates the
+    /// same BFS frontier as the real search without also rayon-scoring every candidate — a
+    /// rollout step only needs to pick one placement uniformly, not rank all of them. Each step
+    /// locks through `Bot::lock_piece` with that step's own `is_spin` flag, so the final
+    /// state's `data.last_sent` reflects the guideline attack for whichever placement happened
+    /// last, and `score_game` (rather than the engine's own spin-unaware scoring) reads it back.
+    fn rollout(game: &Game, weight: &Weights, rng_state: &mut u64) -> Score {
+        let mut state = game.clone();
+
+        for _ in 0..ROLLOUT_DEPTH {
+            if state.get_game_over() {
+                break;
+            }
+
+            let (moves, spins) = Bot::reachable_moves_1d(&mut state);
+            if moves.is_empty() {
+                break;
+            }
+
+            let index = (MctsBot::next_random(rng_state) as usize) % moves.len();
+            do_move_list(&mut state, moves[index].clone());
+            Bot::lock_piece(&mut state, spins[index]);
+        }
+
+        let (board, versus) = Bot::score_game(&state, weight);
+        board + versus
+    }
+
+    /// xorshift64: good enough spread for rollout sampling without pulling in a dependency.
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node_with(visits: u32, total_cost: f32) -> MctsNode {
+        MctsNode {
+            game: Game::new(None),
+            command: vec![],
+            visits,
+            total_cost,
+            children: Vec::new(),
+            unexpanded: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn uct_prioritizes_an_unvisited_child() {
+        let unvisited = node_with(0, 0.0);
+        assert_eq!(MctsBot::uct(&unvisited, 10.0), f32::INFINITY);
+    }
+
+    #[test]
+    fn uct_prefers_lower_mean_cost() {
+        let parent_visits = 16.0;
+        let cheap = node_with(4, -8.0);
+        let expensive = node_with(4, 8.0);
+        assert!(MctsBot::uct(&cheap, parent_visits) > MctsBot::uct(&expensive, parent_visits));
+    }
+
+    #[test]
+    fn uct_matches_the_formula() {
+        let parent_visits = 9.0;
+        let child = node_with(3, -6.0);
+        let expected = 2.0 + EXPLORATION * (parent_visits.ln() / 3.0).sqrt();
+        assert!((MctsBot::uct(&child, parent_visits) - expected).abs() < 1e-6);
+    }
+}